@@ -1,13 +1,14 @@
 //! Health Check Endpoints
 //!
 //! This module defines the health and version endpoints.
-//! - `/health` – general health check
+//! - `/health` – per-service readiness report (liveness: the process is up and answering)
+//! - `/ready` – aggregate readiness check (all dependencies started)
 //! - `/version` – version information about the service
 //!
 //! The endpoints include a `Cache-Control: no-cache` header to prevent caching of responses.
 
 use axum::{
-    Router,
+    Json, Router,
     http::{HeaderValue, StatusCode, header},
     response::IntoResponse,
     routing::get,
@@ -20,8 +21,10 @@ use crate::StartedServices;
 ///
 /// All endpoints have `Cache-Control: no-cache` set.
 pub fn routes(started_services: StartedServices, version_str: String) -> Router {
+    let ready_services = started_services.clone();
     Router::new()
         .route("/health", get(move || health(started_services)))
+        .route("/ready", get(move || ready(ready_services)))
         .route("/version", get(move || version(version_str)))
         .layer(SetResponseHeaderLayer::overriding(
             header::CACHE_CONTROL,
@@ -29,13 +32,21 @@ pub fn routes(started_services: StartedServices, version_str: String) -> Router
         ))
 }
 
-/// General health check endpoint.
+/// Liveness endpoint.
 ///
-/// Returns `200 OK` with a plain `"healthy"` response if all services already started.
-/// Returns `503 Service Unavailable` with a plain `"starting"`response if one of the services did not start yet.
+/// Always returns `200 OK` with a JSON object mapping each registered service name to its
+/// readiness, so operators can see which specific dependency is blocking startup.
 async fn health(started_services: StartedServices) -> impl IntoResponse {
+    (StatusCode::OK, Json(started_services.statuses()))
+}
+
+/// Readiness endpoint.
+///
+/// Returns `200 OK` with a plain `"ready"` response if all services already started.
+/// Returns `503 Service Unavailable` with a plain `"starting"` response if one of the services did not start yet.
+async fn ready(started_services: StartedServices) -> impl IntoResponse {
     if started_services.all_started() {
-        (StatusCode::OK, "healthy")
+        (StatusCode::OK, "ready")
     } else {
         (StatusCode::SERVICE_UNAVAILABLE, "starting")
     }
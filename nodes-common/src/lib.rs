@@ -1,15 +1,59 @@
-use std::sync::{
-    Arc, Mutex,
-    atomic::{AtomicBool, Ordering},
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
 };
 
 use aws_config::Region;
 use aws_sdk_secretsmanager::config::Credentials;
-use tokio::signal;
+use tokio::{
+    signal,
+    sync::{Notify, broadcast},
+};
 use tokio_util::sync::CancellationToken;
 
 pub use git_version;
 
+pub mod secrets;
+
+/// A countdown latch shared by [`StartedServices`]/[`ServiceHandle`] and [`InFlight`]/[`InFlightGuard`].
+///
+/// `increment` adds one pending unit, `decrement` removes one and wakes anyone waiting in
+/// [`Countdown::wait_until_zero`] once the count reaches zero. Factored out so this exact
+/// notify-on-zero logic is implemented in one place instead of twice.
+#[derive(Debug, Clone, Default)]
+struct Countdown {
+    pending: Arc<AtomicUsize>,
+    notify: Arc<Notify>,
+}
+
+impl Countdown {
+    fn increment(&self) {
+        self.pending.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Decrements the count, waking waiters if this was the last pending unit.
+    fn decrement(&self) {
+        if self.pending.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.notify.notify_waiters();
+        }
+    }
+
+    /// Resolves once the count is (and, barring a racing `increment`, stays) zero.
+    async fn wait_until_zero(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.pending.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
 /// Macro to generate version information including the crate name, version, and git hash.
 #[macro_export]
 macro_rules! version_info {
@@ -26,10 +70,11 @@ macro_rules! version_info {
 
 /// A struct that keeps track of the health of all async services started by the service.
 ///
-/// Relevant for the `/health` route. Implementations should call [`StartedServices::new_service`] for their services and set the bool to `true` if the service started successfully.
+/// Relevant for the `/health` and `/ready` routes. Implementations should call [`StartedServices::new_service`] for their services and call [`ServiceHandle::set_ready`] on the returned handle once the service started successfully.
 #[derive(Debug, Clone, Default)]
 pub struct StartedServices {
-    external_service: Arc<Mutex<Vec<Arc<AtomicBool>>>>,
+    external_service: Arc<Mutex<Vec<(String, Arc<AtomicBool>)>>>,
+    countdown: Countdown,
 }
 
 impl StartedServices {
@@ -38,16 +83,23 @@ impl StartedServices {
         Self::default()
     }
 
-    /// Adds a new external service to the bookkeeping struct.
+    /// Adds a new named external service to the bookkeeping struct.
     ///
-    /// Implementations should call this method for every async task that they start. The returned `AtomicBool` should then be set to `true` if the service is ready.
-    pub fn new_service(&self) -> Arc<AtomicBool> {
-        let service = Arc::new(AtomicBool::default());
+    /// Implementations should call this method for every async task that they start, then call
+    /// [`ServiceHandle::set_ready`] on the returned handle once the service started successfully.
+    /// The `name` shows up as-is in the `/health` report, so it should be stable and human-readable.
+    pub fn new_service(&self, name: impl Into<String>) -> ServiceHandle {
+        let flag = Arc::new(AtomicBool::default());
         self.external_service
             .lock()
             .expect("Not poisoned")
-            .push(Arc::clone(&service));
-        service
+            .push((name.into(), Arc::clone(&flag)));
+        self.countdown.increment();
+
+        ServiceHandle {
+            flag,
+            countdown: self.countdown.clone(),
+        }
     }
 
     /// Returns `true` if all services did start.
@@ -56,7 +108,50 @@ impl StartedServices {
             .lock()
             .expect("Not poisoned")
             .iter()
-            .all(|service| service.load(Ordering::Relaxed))
+            .all(|(_, service)| service.load(Ordering::Relaxed))
+    }
+
+    /// Returns the readiness of every registered service, keyed by the name it was registered with.
+    pub fn statuses(&self) -> BTreeMap<String, bool> {
+        self.external_service
+            .lock()
+            .expect("Not poisoned")
+            .iter()
+            .map(|(name, service)| (name.clone(), service.load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    /// Returns a future that resolves exactly once every service registered so far has reported ready.
+    ///
+    /// Useful for gating a "server fully started" log line or delaying the bind of a downstream
+    /// listener until all dependencies are up, without re-polling [`StartedServices::all_started`].
+    pub async fn wait_until_ready(&self) {
+        self.countdown.wait_until_zero().await
+    }
+}
+
+/// A handle to a single service registered with [`StartedServices::new_service`].
+///
+/// Call [`ServiceHandle::set_ready`] once the service started successfully; this both flips the
+/// flag read by `/health`, `/ready` and [`StartedServices::all_started`], and directly drives
+/// [`StartedServices::wait_until_ready`] without any polling in between.
+#[derive(Debug, Clone)]
+pub struct ServiceHandle {
+    flag: Arc<AtomicBool>,
+    countdown: Countdown,
+}
+
+impl ServiceHandle {
+    /// Marks the service as ready. Idempotent: calling this more than once has no further effect.
+    pub fn set_ready(&self) {
+        if !self.flag.swap(true, Ordering::AcqRel) {
+            self.countdown.decrement();
+        }
+    }
+
+    /// Returns `true` if [`ServiceHandle::set_ready`] was already called.
+    pub fn is_ready(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
     }
 }
 
@@ -85,6 +180,151 @@ pub fn spawn_shutdown_task(
     (cancellation_token, is_graceful)
 }
 
+/// Sent on the reload channel returned by [`spawn_signal_task`] whenever the process receives SIGHUP.
+#[derive(Debug, Clone, Copy)]
+pub struct ReloadEvent;
+
+/// Like [`spawn_shutdown_task`], but also listens for SIGHUP and broadcasts a [`ReloadEvent`] on the
+/// returned channel so services can re-read configuration or re-pull secrets without restarting.
+///
+/// On non-unix targets SIGHUP can't be observed, so the reload receiver is still returned but never
+/// fires.
+pub fn spawn_signal_task(
+    shutdown_signal: impl Future<Output = ()> + Send + 'static,
+) -> (CancellationToken, broadcast::Receiver<ReloadEvent>, Arc<AtomicBool>) {
+    let cancellation_token = CancellationToken::new();
+    let is_graceful = Arc::new(AtomicBool::new(false));
+    let (reload_tx, reload_rx) = broadcast::channel(16);
+    let task_token = cancellation_token.clone();
+    tokio::spawn({
+        let is_graceful = Arc::clone(&is_graceful);
+        async move {
+            tokio::pin!(shutdown_signal);
+
+            #[cfg(unix)]
+            let mut hangup = signal::unix::signal(signal::unix::SignalKind::hangup())
+                .expect("failed to install SIGHUP handler");
+
+            loop {
+                #[cfg(unix)]
+                let reload = hangup.recv();
+                #[cfg(not(unix))]
+                let reload = std::future::pending::<Option<()>>();
+
+                tokio::select! {
+                    _ = &mut shutdown_signal => {
+                        tracing::info!("received graceful shutdown");
+                        is_graceful.store(true, Ordering::Relaxed);
+                        task_token.cancel();
+                        return;
+                    }
+                    _ = reload => {
+                        tracing::info!("received SIGHUP, broadcasting reload event");
+                        // Ignore the send error: it just means no one is currently listening.
+                        let _ = reload_tx.send(ReloadEvent);
+                    }
+                    _ = task_token.cancelled() => return,
+                }
+            }
+        }
+    });
+    (cancellation_token, reload_rx, is_graceful)
+}
+
+/// Tracks a dynamic number of in-flight work items for [`spawn_shutdown_task_with_grace`].
+///
+/// Unlike [`StartedServices`], which keeps a permanent, named entry per registered service,
+/// `InFlight` is meant for per-request bookkeeping: [`InFlight::start`] returns a guard that
+/// deregisters itself on drop, so memory and task count stay bounded no matter how many units of
+/// work have passed through over the process lifetime.
+#[derive(Debug, Clone, Default)]
+pub struct InFlight {
+    countdown: Countdown,
+}
+
+impl InFlight {
+    /// Creates a tracker with no in-flight work.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers one unit of in-flight work. Drop the returned guard once it completes.
+    pub fn start(&self) -> InFlightGuard {
+        self.countdown.increment();
+        InFlightGuard {
+            countdown: self.countdown.clone(),
+        }
+    }
+
+    /// Returns a future that resolves exactly once there is no in-flight work outstanding.
+    pub async fn wait_until_quiesced(&self) {
+        self.countdown.wait_until_zero().await
+    }
+}
+
+/// RAII guard for one unit of work registered with [`InFlight::start`].
+///
+/// Dropping the guard marks the work as complete and wakes [`InFlight::wait_until_quiesced`] once
+/// it was the last one outstanding.
+#[derive(Debug)]
+pub struct InFlightGuard {
+    countdown: Countdown,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.countdown.decrement();
+    }
+}
+
+/// Like [`spawn_shutdown_task`], but gives in-flight work up to `grace` to quiesce before the
+/// [`CancellationToken`] is cancelled, implementing a drain-then-force shutdown suitable for
+/// running behind a Kubernetes readiness probe or an ALB.
+///
+/// `in_flight` tracks the work to drain: handlers should hold an [`InFlightGuard`] (from
+/// [`InFlight::start`]) for the duration of each unit of work they want to gate shutdown on. Once
+/// `shutdown_signal` completes, the returned `is_draining` flag is set immediately so health checks
+/// can start failing to drain load balancer traffic, then this task waits up to `grace` for
+/// `in_flight` to report fully quiesced via [`InFlight::wait_until_quiesced`]. The returned
+/// `is_graceful` flag is `true` only if the grace period was not exceeded, so the caller can exit
+/// with a distinct code when the shutdown had to be forced.
+pub fn spawn_shutdown_task_with_grace(
+    shutdown_signal: impl Future<Output = ()> + Send + 'static,
+    in_flight: InFlight,
+    grace: Duration,
+) -> (CancellationToken, Arc<AtomicBool>, Arc<AtomicBool>) {
+    let cancellation_token = CancellationToken::new();
+    let is_draining = Arc::new(AtomicBool::new(false));
+    let is_graceful = Arc::new(AtomicBool::new(false));
+    let task_token = cancellation_token.clone();
+    tokio::spawn({
+        let is_draining = Arc::clone(&is_draining);
+        let is_graceful = Arc::clone(&is_graceful);
+        async move {
+            tokio::select! {
+                _ = shutdown_signal => {}
+                _ = task_token.cancelled() => return,
+            }
+
+            tracing::info!(?grace, "received shutdown signal, draining in-flight work");
+            is_draining.store(true, Ordering::Relaxed);
+
+            if tokio::time::timeout(grace, in_flight.wait_until_quiesced())
+                .await
+                .is_ok()
+            {
+                tracing::info!("in-flight work drained gracefully");
+                is_graceful.store(true, Ordering::Relaxed);
+            } else {
+                tracing::warn!("grace period elapsed before in-flight work drained, forcing shutdown");
+            }
+
+            task_token.cancel();
+        }
+    });
+    (cancellation_token, is_draining, is_graceful)
+}
+
 /// The default shutdown signal for the oprf-service. Triggered when pressing CTRL+C on most systems.
 pub async fn default_shutdown_signal() {
     let ctrl_c = async {
@@ -130,3 +370,137 @@ pub async fn localstack_aws_config() -> aws_config::SdkConfig {
         .load()
         .await
 }
+
+/// Installed as the global allocator when the `dhat-heap` feature is enabled, so heap allocations
+/// across the whole process are tracked for [`ProfilerGuard`].
+#[cfg(feature = "dhat-heap")]
+#[global_allocator]
+static ALLOC: dhat::Alloc = dhat::Alloc;
+
+/// RAII guard for the optional `dhat-heap` profiler.
+///
+/// Create one with [`ProfilerGuard::new`] and hold it for the process lifetime. It does nothing
+/// unless built with the `dhat-heap` feature, so call sites don't need to `#[cfg]` around it. Don't
+/// drop it directly on shutdown; pass it to [`flush_profiler_on_shutdown`] instead, so the profile
+/// is only written out on a graceful termination.
+#[cfg(feature = "dhat-heap")]
+pub struct ProfilerGuard(dhat::Profiler);
+
+#[cfg(not(feature = "dhat-heap"))]
+pub struct ProfilerGuard;
+
+impl ProfilerGuard {
+    /// Installs the heap profiler. A no-op unless built with the `dhat-heap` feature.
+    #[cfg(feature = "dhat-heap")]
+    pub fn new() -> Self {
+        Self(dhat::Profiler::new_heap())
+    }
+
+    /// Installs the heap profiler. A no-op unless built with the `dhat-heap` feature.
+    #[cfg(not(feature = "dhat-heap"))]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ProfilerGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Flushes `guard`'s heap profile to `dhat-heap.json` if `is_graceful` is set, consuming it either way.
+///
+/// Intended to be called with the `is_graceful` flag returned by [`spawn_shutdown_task`] (or
+/// [`spawn_shutdown_task_with_grace`]) right before process exit: a forced shutdown skips the flush,
+/// since there's no guarantee there's time left to write the file. A no-op unless built with the
+/// `dhat-heap` feature.
+pub fn flush_profiler_on_shutdown(guard: ProfilerGuard, is_graceful: &AtomicBool) {
+    if is_graceful.load(Ordering::Relaxed) {
+        drop(guard);
+    } else {
+        // Forget rather than drop: dropping would still run `dhat::Profiler`'s flush-on-drop and
+        // write `dhat-heap.json`, which is exactly what a forced shutdown should skip.
+        std::mem::forget(guard);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn wait_until_ready_resolves_once_all_services_are_ready() {
+        let services = StartedServices::new();
+        let a = services.new_service("a");
+        let b = services.new_service("b");
+        let c = services.new_service("c");
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            a.set_ready();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            b.set_ready();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            c.set_ready();
+        });
+
+        tokio::time::timeout(Duration::from_secs(1), services.wait_until_ready())
+            .await
+            .expect("wait_until_ready should resolve once all services report ready");
+        assert!(services.all_started());
+    }
+
+    #[tokio::test]
+    async fn in_flight_wait_until_quiesced_resolves_once_all_guards_are_dropped() {
+        let in_flight = InFlight::new();
+        let guard_a = in_flight.start();
+        let guard_b = in_flight.start();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            drop(guard_a);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            drop(guard_b);
+        });
+
+        tokio::time::timeout(Duration::from_secs(1), in_flight.wait_until_quiesced())
+            .await
+            .expect("wait_until_quiesced should resolve once all guards are dropped");
+    }
+
+    #[tokio::test]
+    async fn spawn_shutdown_task_with_grace_reports_graceful_when_work_drains_in_time() {
+        let in_flight = InFlight::new();
+        let guard = in_flight.start();
+
+        let (cancellation_token, is_draining, is_graceful) =
+            spawn_shutdown_task_with_grace(std::future::ready(()), in_flight, Duration::from_millis(200));
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(guard);
+        });
+
+        tokio::time::timeout(Duration::from_secs(1), cancellation_token.cancelled())
+            .await
+            .expect("shutdown task should cancel the token");
+        assert!(is_draining.load(Ordering::Relaxed));
+        assert!(is_graceful.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn spawn_shutdown_task_with_grace_forces_shutdown_when_grace_elapses() {
+        let in_flight = InFlight::new();
+        let _guard = in_flight.start(); // deliberately never dropped within the grace window
+
+        let (cancellation_token, is_draining, is_graceful) =
+            spawn_shutdown_task_with_grace(std::future::ready(()), in_flight, Duration::from_millis(20));
+
+        tokio::time::timeout(Duration::from_secs(1), cancellation_token.cancelled())
+            .await
+            .expect("shutdown task should cancel the token even when forced");
+        assert!(is_draining.load(Ordering::Relaxed));
+        assert!(!is_graceful.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,215 @@
+//! AWS Secrets Manager-backed secret loading.
+//!
+//! [`SecretStore`] fetches a fixed set of named secrets, caches them in memory, and refreshes them
+//! on a background interval so long-lived node services can pick up rotated credentials without
+//! restarting. Pair it with [`crate::spawn_signal_task`]'s [`ReloadEvent`](crate::ReloadEvent) to
+//! also refresh immediately on SIGHUP.
+
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+
+use aws_sdk_secretsmanager::Client;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+
+use crate::StartedServices;
+
+/// A secret value fetched from AWS Secrets Manager.
+///
+/// Deliberately does not implement `Debug`/`Display` with the value itself, so a stray
+/// `{:?}`/log statement can't leak it.
+#[derive(Clone)]
+pub struct SecretString(Arc<str>);
+
+impl SecretString {
+    /// Returns the underlying secret value.
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(..)")
+    }
+}
+
+/// Error returned by [`SecretStore::new`] and [`SecretStore::refresh_now`].
+#[derive(Debug)]
+pub enum SecretStoreError {
+    /// Fetching the named secret from Secrets Manager failed.
+    Fetch(String, aws_sdk_secretsmanager::Error),
+    /// Secrets Manager returned the secret without a string value (e.g. it is binary-only).
+    MissingValue(String),
+}
+
+impl fmt::Display for SecretStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretStoreError::Fetch(name, err) => {
+                write!(f, "failed to fetch secret {name:?}: {err}")
+            }
+            SecretStoreError::MissingValue(name) => {
+                write!(f, "secret {name:?} has no string value")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SecretStoreError {}
+
+/// Loads and caches named secrets from AWS Secrets Manager, refreshing them in the background.
+///
+/// Construct with [`SecretStore::new`], which fetches every configured secret once, registers a
+/// background refresh task with a [`StartedServices`] instance so `/health` reports its readiness,
+/// and cancels the refresh task through the supplied [`CancellationToken`]. Pass the config
+/// returned by [`crate::localstack_aws_config`] to exercise the same code path against LocalStack.
+#[derive(Clone)]
+pub struct SecretStore {
+    client: Client,
+    names: Arc<Vec<String>>,
+    cache: Arc<RwLock<HashMap<String, SecretString>>>,
+}
+
+impl SecretStore {
+    /// Creates a new secret store, performs an initial fetch of `names`, and spawns a background
+    /// task that refreshes them every `refresh_interval` until `cancellation_token` is cancelled.
+    ///
+    /// Registers a service with `started_services` before the initial fetch so `/health` shows
+    /// `secret-store` as not-yet-ready (rather than absent) while that fetch is in flight, and
+    /// reports it ready only once the fetch succeeds.
+    pub async fn new(
+        sdk_config: &aws_config::SdkConfig,
+        names: Vec<String>,
+        refresh_interval: Duration,
+        started_services: &StartedServices,
+        cancellation_token: CancellationToken,
+    ) -> Result<Self, SecretStoreError> {
+        let store = Self {
+            client: Client::new(sdk_config),
+            names: Arc::new(names),
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        };
+
+        let handle = started_services.new_service("secret-store");
+        store.refresh_now().await?;
+        handle.set_ready();
+
+        tokio::spawn({
+            let store = store.clone();
+            async move {
+                loop {
+                    tokio::select! {
+                        _ = tokio::time::sleep(refresh_interval) => {
+                            if let Err(err) = store.refresh_now().await {
+                                tracing::warn!(%err, "failed to refresh secrets, keeping cached values");
+                            }
+                        }
+                        _ = cancellation_token.cancelled() => return,
+                    }
+                }
+            }
+        });
+
+        Ok(store)
+    }
+
+    /// Returns the cached value for `name`, if it has been fetched successfully at least once.
+    pub async fn get(&self, name: &str) -> Option<SecretString> {
+        self.cache.read().await.get(name).cloned()
+    }
+
+    /// Re-fetches all configured secrets from Secrets Manager and replaces the cache.
+    ///
+    /// Intended to be called from a SIGHUP reload handler so operators can rotate secrets live, in
+    /// addition to the automatic background refresh.
+    pub async fn refresh_now(&self) -> Result<(), SecretStoreError> {
+        let mut fresh = HashMap::with_capacity(self.names.len());
+        for name in self.names.iter() {
+            let response = self
+                .client
+                .get_secret_value()
+                .secret_id(name)
+                .send()
+                .await
+                .map_err(|err| SecretStoreError::Fetch(name.clone(), err.into()))?;
+            let value = response
+                .secret_string()
+                .ok_or_else(|| SecretStoreError::MissingValue(name.clone()))?;
+            fresh.insert(name.clone(), SecretString(Arc::from(value)));
+        }
+        *self.cache.write().await = fresh;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::*;
+    use crate::{StartedServices, localstack_aws_config};
+
+    fn unique_secret_name() -> String {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock is after UNIX_EPOCH")
+            .as_nanos();
+        format!("nodes-common-test-secret-{nanos}")
+    }
+
+    #[tokio::test]
+    async fn fetches_and_refreshes_secrets_from_localstack() {
+        let sdk_config = localstack_aws_config().await;
+        let client = Client::new(&sdk_config);
+        let name = unique_secret_name();
+
+        client
+            .create_secret()
+            .name(&name)
+            .secret_string("first-value")
+            .send()
+            .await
+            .expect("failed to create secret in LocalStack");
+
+        let cancellation_token = CancellationToken::new();
+        let store = SecretStore::new(
+            &sdk_config,
+            vec![name.clone()],
+            Duration::from_secs(3600),
+            &StartedServices::new(),
+            cancellation_token.clone(),
+        )
+        .await
+        .expect("failed to construct SecretStore");
+
+        assert_eq!(
+            store.get(&name).await.map(|s| s.expose_secret().to_owned()),
+            Some("first-value".to_owned())
+        );
+
+        client
+            .put_secret_value()
+            .secret_id(&name)
+            .secret_string("second-value")
+            .send()
+            .await
+            .expect("failed to update secret in LocalStack");
+
+        store.refresh_now().await.expect("refresh_now failed");
+
+        assert_eq!(
+            store.get(&name).await.map(|s| s.expose_secret().to_owned()),
+            Some("second-value".to_owned())
+        );
+
+        client
+            .delete_secret()
+            .secret_id(&name)
+            .force_delete_without_recovery(true)
+            .send()
+            .await
+            .expect("failed to clean up secret in LocalStack");
+
+        cancellation_token.cancel();
+    }
+}